@@ -0,0 +1,45 @@
+use std::str;
+
+/// A binary-safe string of bytes, used for macaroon identifiers, caveat ids,
+/// and verifier ids, which are not guaranteed to be valid UTF8 (verifier ids
+/// in particular are typically encrypted blobs).
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteString(pub Vec<u8>);
+
+impl ByteString {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        str::from_utf8(&self.0).ok()
+    }
+}
+
+impl<'a> From<&'a str> for ByteString {
+    fn from(value: &'a str) -> ByteString {
+        ByteString(value.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for ByteString {
+    fn from(value: String) -> ByteString {
+        ByteString(value.into_bytes())
+    }
+}
+
+impl From<Vec<u8>> for ByteString {
+    fn from(value: Vec<u8>) -> ByteString {
+        ByteString(value)
+    }
+}
+
+impl<'a> From<&'a [u8]> for ByteString {
+    fn from(value: &'a [u8]) -> ByteString {
+        ByteString(value.to_vec())
+    }
+}
@@ -0,0 +1,141 @@
+use ciborium::value::Value;
+
+use super::byte_string::ByteString;
+use super::error::MacaroonError;
+use super::macaroon::{Caveat, Macaroon};
+
+fn macaroon_to_value(macaroon: &Macaroon) -> Value {
+    let mut map: Vec<(Value, Value)> = Vec::new();
+    map.push((Value::Text(String::from("v")), Value::Integer(2.into())));
+    if !macaroon.location.is_empty() {
+        map.push((Value::Text(String::from("l")), Value::Text(macaroon.location.clone())));
+    }
+    map.push((Value::Text(String::from("i")), Value::Bytes(macaroon.identifier.as_bytes().to_vec())));
+    map.push((Value::Text(String::from("c")),
+               Value::Array(macaroon.caveats.iter().map(caveat_to_value).collect())));
+    map.push((Value::Text(String::from("s")), Value::Bytes(macaroon.signature.clone())));
+    Value::Map(map)
+}
+
+fn caveat_to_value(caveat: &Caveat) -> Value {
+    let mut map: Vec<(Value, Value)> = Vec::new();
+    map.push((Value::Text(String::from("i")), Value::Bytes(caveat.id.as_bytes().to_vec())));
+    if let Some(ref location) = caveat.location {
+        map.push((Value::Text(String::from("l")), Value::Text(location.clone())));
+    }
+    if let Some(ref verifier_id) = caveat.verifier_id {
+        map.push((Value::Text(String::from("v")), Value::Bytes(verifier_id.as_bytes().to_vec())));
+    }
+    Value::Map(map)
+}
+
+pub fn serialize_cbor(macaroon: &Macaroon) -> Result<Vec<u8>, MacaroonError> {
+    let mut buffer: Vec<u8> = Vec::new();
+    match ciborium::ser::into_writer(&macaroon_to_value(macaroon), &mut buffer) {
+        Ok(()) => Ok(buffer),
+        Err(error) => Err(MacaroonError::SerializationError(error.to_string())),
+    }
+}
+
+fn value_as_map(value: &Value) -> Result<&Vec<(Value, Value)>, MacaroonError> {
+    match *value {
+        Value::Map(ref map) => Ok(map),
+        _ => Err(MacaroonError::DeserializationError(String::from("Expected CBOR map"))),
+    }
+}
+
+fn map_get<'r>(map: &'r [(Value, Value)], key: &str) -> Option<&'r Value> {
+    for (k, v) in map {
+        if let Value::Text(ref text) = *k {
+            if text == key {
+                return Some(v);
+            }
+        }
+    }
+    None
+}
+
+fn value_as_bytes(value: &Value) -> Result<Vec<u8>, MacaroonError> {
+    match *value {
+        Value::Bytes(ref bytes) => Ok(bytes.clone()),
+        _ => Err(MacaroonError::DeserializationError(String::from("Expected CBOR byte string"))),
+    }
+}
+
+fn value_as_text(value: &Value) -> Result<String, MacaroonError> {
+    match *value {
+        Value::Text(ref text) => Ok(text.clone()),
+        _ => Err(MacaroonError::DeserializationError(String::from("Expected CBOR text string"))),
+    }
+}
+
+pub fn deserialize_cbor(data: &[u8]) -> Result<Macaroon, MacaroonError> {
+    let value: Value = match ciborium::de::from_reader(data) {
+        Ok(value) => value,
+        Err(error) => return Err(MacaroonError::DeserializationError(error.to_string())),
+    };
+    let map = try!(value_as_map(&value));
+    let mut macaroon: Macaroon = Default::default();
+
+    if let Some(location) = map_get(map, "l") {
+        macaroon.location = try!(value_as_text(location));
+    }
+    macaroon.identifier = match map_get(map, "i") {
+        Some(value) => ByteString::from(try!(value_as_bytes(value))),
+        None => return Err(MacaroonError::DeserializationError(String::from("Missing identifier field"))),
+    };
+
+    if let Some(Value::Array(caveats)) = map_get(map, "c") {
+        for caveat_value in caveats {
+            let caveat_map = try!(value_as_map(caveat_value));
+            let id = match map_get(caveat_map, "i") {
+                Some(value) => ByteString::from(try!(value_as_bytes(value))),
+                None => {
+                    return Err(MacaroonError::DeserializationError(String::from("Missing caveat identifier field")))
+                }
+            };
+            let location = match map_get(caveat_map, "l") {
+                Some(location) => Some(try!(value_as_text(location))),
+                None => None,
+            };
+            let verifier_id = match map_get(caveat_map, "v") {
+                Some(verifier_id) => Some(ByteString::from(try!(value_as_bytes(verifier_id)))),
+                None => None,
+            };
+            macaroon.caveats.push(Caveat { id, location, verifier_id });
+        }
+    }
+
+    macaroon.signature = match map_get(map, "s") {
+        Some(value) => try!(value_as_bytes(value)),
+        None => return Err(MacaroonError::DeserializationError(String::from("Missing signature field"))),
+    };
+
+    Ok(macaroon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::macaroon::Macaroon;
+
+    const SIGNATURE_V1: [u8; 32] = [124, 222, 231, 146, 81, 28, 91, 198, 245, 40, 72, 88, 5, 223,
+                                    233, 178, 78, 120, 94, 40, 226, 169, 147, 1, 249, 215, 17,
+                                    198, 9, 227, 142, 247];
+
+    #[test]
+    fn test_serialize_deserialize_cbor() {
+        let macaroon = Macaroon::create("http://example.org/", SIGNATURE_V1, "keyid").unwrap();
+        let serialized = macaroon.serialize_cbor().unwrap();
+        let other = Macaroon::deserialize_cbor(&serialized).unwrap();
+        assert_eq!(macaroon, other);
+    }
+
+    #[test]
+    fn test_serialize_deserialize_cbor_with_binary_caveat_id() {
+        let mut macaroon = Macaroon::create("http://example.org/", SIGNATURE_V1, "keyid").unwrap();
+        macaroon.add_first_party_caveat(&[0xff, 0x00, 0xfe, 0x01][..]);
+        let serialized = macaroon.serialize_cbor().unwrap();
+        let other = Macaroon::deserialize_cbor(&serialized).unwrap();
+        assert_eq!(macaroon, other);
+    }
+}
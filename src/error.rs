@@ -0,0 +1,35 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacaroonError {
+    SerializationError(String),
+    DeserializationError(String),
+    VerificationError(String),
+}
+
+impl fmt::Display for MacaroonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MacaroonError::SerializationError(ref message) => {
+                write!(f, "Error serializing macaroon: {}", message)
+            }
+            MacaroonError::DeserializationError(ref message) => {
+                write!(f, "Error deserializing macaroon: {}", message)
+            }
+            MacaroonError::VerificationError(ref message) => {
+                write!(f, "Error verifying macaroon: {}", message)
+            }
+        }
+    }
+}
+
+impl Error for MacaroonError {
+    fn description(&self) -> &str {
+        match *self {
+            MacaroonError::SerializationError(ref message) => message,
+            MacaroonError::DeserializationError(ref message) => message,
+            MacaroonError::VerificationError(ref message) => message,
+        }
+    }
+}
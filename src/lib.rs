@@ -0,0 +1,23 @@
+// This crate targets the 2015-edition API surface (rust-crypto, rustc-serialize,
+// the `try!` macro, bare trait objects) rather than its modern replacements.
+#![allow(deprecated)]
+#![allow(bare_trait_objects)]
+
+extern crate crypto;
+extern crate rand;
+extern crate serialize;
+#[cfg(feature = "cbor")]
+extern crate ciborium;
+
+pub mod byte_string;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod error;
+pub mod macaroon;
+pub mod serialization;
+pub mod verifier;
+
+pub use byte_string::ByteString;
+pub use error::MacaroonError;
+pub use macaroon::{Caveat, Format, Macaroon, MacaroonKey};
+pub use verifier::Verifier;
@@ -0,0 +1,174 @@
+use crypto::aes::{ctr, KeySize};
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha256;
+use crypto::symmetriccipher::SynchronousStreamCipher;
+use rand::Rng;
+use rand::os::OsRng;
+
+use super::byte_string::ByteString;
+use super::error::MacaroonError;
+use super::serialization;
+
+const NONCE_SIZE: usize = 16;
+
+pub const KEY_SIZE: usize = 32;
+pub type MacaroonKey = [u8; KEY_SIZE];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    V1,
+    V2,
+    V2J,
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Caveat {
+    pub id: ByteString,
+    pub verifier_id: Option<ByteString>,
+    pub location: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Macaroon {
+    pub location: String,
+    pub identifier: ByteString,
+    pub signature: Vec<u8>,
+    pub caveats: Vec<Caveat>,
+}
+
+pub(crate) fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut hmac = Hmac::new(Sha256::new(), key);
+    hmac.input(data);
+    hmac.result().code().to_vec()
+}
+
+// Encrypts `plaintext` (a third-party caveat's root key) under `key` (the
+// macaroon's current signature), producing the verifier id carried in the
+// caveat: a random nonce followed by the AES-256-CTR ciphertext.
+fn encrypt(key: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let mut nonce = vec![0u8; NONCE_SIZE];
+    OsRng::new().expect("failed to initialize OS RNG").fill_bytes(&mut nonce);
+    let mut ciphertext = vec![0u8; plaintext.len()];
+    ctr(KeySize::KeySize256, key, &nonce).process(plaintext, &mut ciphertext);
+    nonce.extend_from_slice(&ciphertext);
+    nonce
+}
+
+impl Macaroon {
+    pub fn create<I: Into<ByteString>>(location: &str,
+                                        key: MacaroonKey,
+                                        identifier: I)
+                                        -> Result<Macaroon, MacaroonError> {
+        let identifier = identifier.into();
+        Ok(Macaroon {
+            location: String::from(location),
+            signature: hmac(&key, identifier.as_bytes()),
+            identifier,
+            caveats: Vec::new(),
+        })
+    }
+
+    pub fn add_first_party_caveat<I: Into<ByteString>>(&mut self, predicate: I) {
+        let predicate = predicate.into();
+        self.signature = hmac(&self.signature, predicate.as_bytes());
+        self.caveats.push(Caveat {
+            id: predicate,
+            verifier_id: None,
+            location: None,
+        });
+    }
+
+    /// Creates a discharge macaroon for a third-party caveat: a root macaroon
+    /// anchored by the caveat's root key rather than a location-owned key.
+    pub fn create_discharge<I: Into<ByteString>>(location: &str,
+                                                  key: MacaroonKey,
+                                                  identifier: I)
+                                                  -> Result<Macaroon, MacaroonError> {
+        Macaroon::create(location, key, identifier)
+    }
+
+    /// Adds a third-party caveat: `key` is the root key of the discharge
+    /// macaroon the third party at `location` will issue for `id`. `key` is
+    /// encrypted under the macaroon's current signature to produce the vid
+    /// carried alongside the caveat.
+    pub fn add_third_party_caveat<I: Into<ByteString>>(&mut self,
+                                                        location: &str,
+                                                        key: MacaroonKey,
+                                                        id: I) {
+        let id = id.into();
+        let verifier_id = encrypt(&self.signature, &key);
+        let mut hmac_input = verifier_id.clone();
+        hmac_input.extend_from_slice(id.as_bytes());
+        self.signature = hmac(&self.signature, &hmac_input);
+        self.caveats.push(Caveat {
+            id,
+            verifier_id: Some(ByteString::from(verifier_id)),
+            location: Some(String::from(location)),
+        });
+    }
+
+    /// Binds a discharge macaroon to this root macaroon, so that the result
+    /// is only valid when presented alongside this specific root.
+    pub fn bind(&self, root: &Macaroon) -> Macaroon {
+        let mut bound = self.clone();
+        bound.signature = hmac(&root.signature, &self.signature);
+        bound
+    }
+
+    pub fn serialize(&self, format: Format) -> Result<String, MacaroonError> {
+        match format {
+            Format::V1 => serialization::serialize_v1(self),
+            Format::V2J => serialization::serialize_v2j(self),
+            Format::V2 => {
+                Err(MacaroonError::SerializationError(String::from("V2 is a binary format; use serialize_v2")))
+            }
+            #[cfg(feature = "cbor")]
+            Format::Cbor => {
+                Err(MacaroonError::SerializationError(String::from("CBOR is a binary format; use serialize_cbor")))
+            }
+        }
+    }
+
+    /// Serializes to the raw (non-base64) libmacaroons V2 binary format.
+    pub fn serialize_v2(&self) -> Result<Vec<u8>, MacaroonError> {
+        serialization::serialize_v2(self)
+    }
+
+    /// Serializes to the CBOR format (feature `cbor`).
+    #[cfg(feature = "cbor")]
+    pub fn serialize_cbor(&self) -> Result<Vec<u8>, MacaroonError> {
+        super::cbor::serialize_cbor(self)
+    }
+
+    pub fn deserialize(data: &str) -> Result<Macaroon, MacaroonError> {
+        serialization::deserialize_v1(data)
+    }
+
+    pub fn deserialize_as(data: &str, format: Format) -> Result<Macaroon, MacaroonError> {
+        match format {
+            Format::V1 => serialization::deserialize_v1(data),
+            Format::V2J => serialization::deserialize_v2j(data),
+            Format::V2 => {
+                Err(MacaroonError::DeserializationError(String::from("V2 is a binary format; use deserialize_v2")))
+            }
+            #[cfg(feature = "cbor")]
+            Format::Cbor => {
+                Err(MacaroonError::DeserializationError(String::from("CBOR is a binary format; use deserialize_cbor")))
+            }
+        }
+    }
+
+    /// Deserializes from the raw (non-base64) libmacaroons V2 binary format.
+    pub fn deserialize_v2(data: &[u8]) -> Result<Macaroon, MacaroonError> {
+        serialization::deserialize_v2(data)
+    }
+
+    /// Deserializes from the CBOR format (feature `cbor`).
+    #[cfg(feature = "cbor")]
+    pub fn deserialize_cbor(data: &[u8]) -> Result<Macaroon, MacaroonError> {
+        super::cbor::deserialize_cbor(data)
+    }
+}
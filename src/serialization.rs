@@ -1,15 +1,18 @@
 use serialize::base64::{STANDARD, ToBase64, FromBase64};
+use serialize::json::Json;
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::str;
+use super::byte_string::ByteString;
 use super::macaroon::{Caveat, Macaroon};
 use super::error::MacaroonError;
 
-const LOCATION: &'static str = "location";
-const IDENTIFIER: &'static str = "identifier";
-const SIGNATURE: &'static str = "signature";
-const CID: &'static str = "cid";
-const VID: &'static str = "vid";
-const CL: &'static str = "cl";
+const LOCATION: &str = "location";
+const IDENTIFIER: &str = "identifier";
+const SIGNATURE: &str = "signature";
+const CID: &str = "cid";
+const VID: &str = "vid";
+const CL: &str = "cl";
 
 const HEADER_SIZE: usize = 4;
 
@@ -31,13 +34,10 @@ fn to_hex_char(value: u8) -> u8 {
 }
 
 fn packet_header(size: usize) -> Vec<u8> {
-    let mut header: Vec<u8> = Vec::new();
-    header.push(to_hex_char(((size >> 12) & 15) as u8));
-    header.push(to_hex_char(((size >> 8) & 15) as u8));
-    header.push(to_hex_char(((size >> 4) & 15) as u8));
-    header.push(to_hex_char((size & 15) as u8));
-
-    header
+    vec![to_hex_char(((size >> 12) & 15) as u8),
+         to_hex_char(((size >> 8) & 15) as u8),
+         to_hex_char(((size >> 4) & 15) as u8),
+         to_hex_char((size & 15) as u8)]
 }
 
 #[allow(unused_variables)]
@@ -47,29 +47,107 @@ pub fn serialize_v1(macaroon: &Macaroon) -> Result<String, MacaroonError> {
     serialized.extend(serialize_as_packet(IDENTIFIER, macaroon.identifier.as_bytes()));
     for caveat in &macaroon.caveats {
         serialized.extend(serialize_as_packet(CID, caveat.id.as_bytes()));
-        match caveat.verifier_id {
-            Some(ref verifier_id) => {
-                serialized.extend(serialize_as_packet(VID, verifier_id.as_bytes()))
-            }
-            None => (),
+        if let Some(ref verifier_id) = caveat.verifier_id {
+            serialized.extend(serialize_as_packet(VID, verifier_id.as_bytes()))
         }
-        match caveat.location {
-            Some(ref location) => serialized.extend(serialize_as_packet(CL, location.as_bytes())),
-            None => (),
+        if let Some(ref location) = caveat.location {
+            serialized.extend(serialize_as_packet(CL, location.as_bytes()))
         }
     }
     serialized.extend(serialize_as_packet(SIGNATURE, &macaroon.signature));
     Ok(serialized.to_base64(STANDARD))
 }
 
-#[allow(unused_variables)]
-pub fn serialize_v2(macaroon: &Macaroon) -> Result<String, MacaroonError> {
-    Ok("".to_string())
+const V2_VERSION: u8 = 2;
+const V2_FIELD_EOS: u64 = 0;
+const V2_FIELD_LOCATION: u64 = 1;
+const V2_FIELD_IDENTIFIER: u64 = 2;
+const V2_FIELD_VID: u64 = 4;
+const V2_FIELD_SIGNATURE: u64 = 6;
+
+fn encode_varint(mut value: u64, out: &mut Vec<u8>) {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if value == 0 {
+            break;
+        }
+    }
+}
+
+fn encode_v2_field(field_type: u64, value: &[u8], out: &mut Vec<u8>) {
+    encode_varint(field_type, out);
+    encode_varint(value.len() as u64, out);
+    out.extend_from_slice(value);
+}
+
+fn encode_v2_eos(out: &mut Vec<u8>) {
+    encode_varint(V2_FIELD_EOS, out);
+}
+
+pub fn serialize_v2(macaroon: &Macaroon) -> Result<Vec<u8>, MacaroonError> {
+    let mut serialized: Vec<u8> = Vec::new();
+    serialized.push(V2_VERSION);
+    if !macaroon.location.is_empty() {
+        encode_v2_field(V2_FIELD_LOCATION, macaroon.location.as_bytes(), &mut serialized);
+    }
+    encode_v2_field(V2_FIELD_IDENTIFIER, macaroon.identifier.as_bytes(), &mut serialized);
+    encode_v2_eos(&mut serialized);
+    for caveat in &macaroon.caveats {
+        if let Some(ref location) = caveat.location {
+            encode_v2_field(V2_FIELD_LOCATION, location.as_bytes(), &mut serialized);
+        }
+        encode_v2_field(V2_FIELD_IDENTIFIER, caveat.id.as_bytes(), &mut serialized);
+        if let Some(ref verifier_id) = caveat.verifier_id {
+            encode_v2_field(V2_FIELD_VID, verifier_id.as_bytes(), &mut serialized);
+        }
+        encode_v2_eos(&mut serialized);
+    }
+    encode_v2_eos(&mut serialized);
+    encode_v2_field(V2_FIELD_SIGNATURE, &macaroon.signature, &mut serialized);
+    Ok(serialized)
+}
+
+fn insert_text_field(obj: &mut BTreeMap<String, Json>, key: &str, bytes: &[u8]) {
+    match str::from_utf8(bytes) {
+        Ok(text) => {
+            obj.insert(key.to_string(), Json::String(text.to_string()));
+        }
+        Err(_) => {
+            obj.insert(format!("{}64", key), Json::String(bytes.to_base64(STANDARD)));
+        }
+    }
 }
 
-#[allow(unused_variables)]
 pub fn serialize_v2j(macaroon: &Macaroon) -> Result<String, MacaroonError> {
-    Ok("".to_string())
+    let mut obj: BTreeMap<String, Json> = BTreeMap::new();
+    obj.insert("v".to_string(), Json::U64(2));
+    if !macaroon.location.is_empty() {
+        insert_text_field(&mut obj, "l", macaroon.location.as_bytes());
+    }
+    insert_text_field(&mut obj, "i", macaroon.identifier.as_bytes());
+
+    let mut caveats: Vec<Json> = Vec::new();
+    for caveat in &macaroon.caveats {
+        let mut caveat_obj: BTreeMap<String, Json> = BTreeMap::new();
+        insert_text_field(&mut caveat_obj, "i", caveat.id.as_bytes());
+        if let Some(ref location) = caveat.location {
+            insert_text_field(&mut caveat_obj, "l", location.as_bytes());
+        }
+        if let Some(ref verifier_id) = caveat.verifier_id {
+            caveat_obj.insert("v64".to_string(),
+                               Json::String(verifier_id.as_bytes().to_base64(STANDARD)));
+        }
+        caveats.push(Json::Object(caveat_obj));
+    }
+    obj.insert("c".to_string(), Json::Array(caveats));
+    obj.insert("s64".to_string(), Json::String(macaroon.signature.to_base64(STANDARD)));
+
+    Ok(Json::Object(obj).to_string())
 }
 
 macro_rules! try_utf8 {
@@ -85,6 +163,13 @@ macro_rules! try_utf8 {
     )
 }
 
+fn strip_trailing_newline(bytes: &[u8]) -> &[u8] {
+    match bytes.last() {
+        Some(&b'\n') => &bytes[..bytes.len() - 1],
+        _ => bytes,
+    }
+}
+
 fn base64_decode(base64: &str) -> Result<Vec<u8>, MacaroonError> {
     match base64.from_base64() {
         Ok(value) => Ok(value),
@@ -97,10 +182,8 @@ struct Packet {
     value: Vec<u8>,
 }
 
-fn deserialize_as_packets<'r>(data: &'r [u8],
-                              mut packets: Vec<Packet>)
-                              -> Result<Vec<Packet>, MacaroonError> {
-    if data.len() == 0 {
+fn deserialize_as_packets(data: &[u8], mut packets: Vec<Packet>) -> Result<Vec<Packet>, MacaroonError> {
+    if data.is_empty() {
         return Ok(packets);
     }
     let size: usize = match str::from_utf8(&data[..4]) {
@@ -125,9 +208,9 @@ fn deserialize_as_packets<'r>(data: &'r [u8],
 }
 
 fn get_split_index(packet: &[u8]) -> Result<usize, MacaroonError> {
-    match packet.iter().position(|&r| r == ' ' as u8) {
+    match packet.iter().position(|&r| r == b' ') {
         Some(index) => Ok(index),
-        None => return Err(MacaroonError::DeserializationError(String::from("Key/value error"))),
+        None => Err(MacaroonError::DeserializationError(String::from("Key/value error"))),
     }
 }
 
@@ -136,28 +219,30 @@ pub fn deserialize_v1(base64: &str) -> Result<Macaroon, MacaroonError> {
     let mut macaroon: Macaroon = Default::default();
     let mut caveat: Caveat = Default::default();
     for packet in try!(deserialize_as_packets(data.as_slice(), Vec::new())) {
-        println!("{:?}", packet.key);
         match packet.key.as_str() {
             LOCATION => macaroon.location = String::from(try_utf8!(&packet.value).trim()),
-            IDENTIFIER => macaroon.identifier = String::from(try_utf8!(&packet.value).trim()),
+            IDENTIFIER => macaroon.identifier = ByteString::from(strip_trailing_newline(&packet.value)),
             SIGNATURE => {
                 if !caveat.id.is_empty() {
                     macaroon.caveats.push(caveat);
                     caveat = Default::default();
                 }
+                if packet.value.len() < 32 {
+                    return Err(MacaroonError::DeserializationError(String::from("Signature too short")));
+                }
                 let mut signature: Vec<u8> = Vec::new();
                 signature.extend_from_slice(&packet.value[..32]);
                 macaroon.signature = signature;
             }
             CID => {
                 if caveat.id.is_empty() {
-                    caveat.id = String::from(try_utf8!(&packet.value).trim());
+                    caveat.id = ByteString::from(strip_trailing_newline(&packet.value));
                 } else {
                     macaroon.caveats.push(caveat);
                     caveat = Default::default();
                 }
             }
-            VID => caveat.verifier_id = Some(String::from(try_utf8!(&packet.value).trim())),
+            VID => caveat.verifier_id = Some(ByteString::from(strip_trailing_newline(&packet.value))),
             CL => caveat.location = Some(String::from(try_utf8!(&packet.value).trim())),
             _ => return Err(MacaroonError::DeserializationError(String::from("Unknown key"))),
         };
@@ -165,23 +250,182 @@ pub fn deserialize_v1(base64: &str) -> Result<Macaroon, MacaroonError> {
     Ok(macaroon)
 }
 
-#[allow(unused_variables)]
-pub fn deserialize_v2(data: &str) -> Result<Macaroon, MacaroonError> {
-    unimplemented!()
+fn decode_varint(data: &[u8], pos: &mut usize) -> Result<u64, MacaroonError> {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = match data.get(*pos) {
+            Some(byte) => *byte,
+            None => {
+                return Err(MacaroonError::DeserializationError(String::from("Truncated varint")))
+            }
+        };
+        *pos += 1;
+        if shift >= 64 {
+            return Err(MacaroonError::DeserializationError(String::from("varint too long")));
+        }
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+fn decode_v2_field(data: &[u8], pos: &mut usize) -> Result<(u64, Vec<u8>), MacaroonError> {
+    let field_type = try!(decode_varint(data, pos));
+    if field_type == V2_FIELD_EOS {
+        return Ok((field_type, Vec::new()));
+    }
+    let len = try!(decode_varint(data, pos)) as usize;
+    let end = match pos.checked_add(len) {
+        Some(end) if end <= data.len() => end,
+        _ => return Err(MacaroonError::DeserializationError(String::from("Truncated field"))),
+    };
+    let value = data[*pos..end].to_vec();
+    *pos += len;
+    Ok((field_type, value))
+}
+
+pub fn deserialize_v2(data: &[u8]) -> Result<Macaroon, MacaroonError> {
+    if data.is_empty() || data[0] != V2_VERSION {
+        return Err(MacaroonError::DeserializationError(String::from("Unsupported V2 version")));
+    }
+    let mut pos = 1;
+    let mut macaroon: Macaroon = Default::default();
+
+    let (mut field_type, mut value) = try!(decode_v2_field(data, &mut pos));
+    if field_type == V2_FIELD_LOCATION {
+        macaroon.location = try_utf8!(&value);
+        let next = try!(decode_v2_field(data, &mut pos));
+        field_type = next.0;
+        value = next.1;
+    }
+    if field_type != V2_FIELD_IDENTIFIER {
+        return Err(MacaroonError::DeserializationError(String::from("Expected identifier field")));
+    }
+    macaroon.identifier = ByteString::from(value);
+    let (eos_type, _) = try!(decode_v2_field(data, &mut pos));
+    if eos_type != V2_FIELD_EOS {
+        return Err(MacaroonError::DeserializationError(String::from("Expected EOS after header")));
+    }
+
+    loop {
+        let (mut field_type, mut value) = try!(decode_v2_field(data, &mut pos));
+        if field_type == V2_FIELD_EOS {
+            break;
+        }
+        let mut caveat: Caveat = Default::default();
+        if field_type == V2_FIELD_LOCATION {
+            caveat.location = Some(try_utf8!(&value));
+            let next = try!(decode_v2_field(data, &mut pos));
+            field_type = next.0;
+            value = next.1;
+        }
+        if field_type != V2_FIELD_IDENTIFIER {
+            return Err(MacaroonError::DeserializationError(String::from("Expected caveat identifier field")));
+        }
+        caveat.id = ByteString::from(value);
+        let (mut field_type, mut value) = try!(decode_v2_field(data, &mut pos));
+        if field_type == V2_FIELD_VID {
+            caveat.verifier_id = Some(ByteString::from(value));
+            let next = try!(decode_v2_field(data, &mut pos));
+            field_type = next.0;
+            value = next.1;
+        }
+        let _ = value;
+        if field_type != V2_FIELD_EOS {
+            return Err(MacaroonError::DeserializationError(String::from("Expected EOS after caveat")));
+        }
+        macaroon.caveats.push(caveat);
+    }
+
+    let (sig_type, sig_value) = try!(decode_v2_field(data, &mut pos));
+    if sig_type != V2_FIELD_SIGNATURE {
+        return Err(MacaroonError::DeserializationError(String::from("Expected signature field")));
+    }
+    macaroon.signature = sig_value;
+
+    Ok(macaroon)
+}
+
+fn json_as_str(json: &Json) -> Result<&str, MacaroonError> {
+    match json.as_string() {
+        Some(value) => Ok(value),
+        None => Err(MacaroonError::DeserializationError(String::from("Expected JSON string"))),
+    }
+}
+
+fn get_text_field(json: &Json, key: &str) -> Result<Option<String>, MacaroonError> {
+    if let Some(value) = json.find(key) {
+        return Ok(Some(String::from(try!(json_as_str(value)))));
+    }
+    let b64_key = format!("{}64", key);
+    if let Some(value) = json.find(&b64_key) {
+        let bytes = try!(base64_decode(try!(json_as_str(value))));
+        return Ok(Some(try_utf8!(&bytes)));
+    }
+    Ok(None)
+}
+
+fn get_byte_field(json: &Json, key: &str) -> Result<Option<ByteString>, MacaroonError> {
+    if let Some(value) = json.find(key) {
+        return Ok(Some(ByteString::from(try!(json_as_str(value)))));
+    }
+    let b64_key = format!("{}64", key);
+    if let Some(value) = json.find(&b64_key) {
+        let bytes = try!(base64_decode(try!(json_as_str(value))));
+        return Ok(Some(ByteString::from(bytes)));
+    }
+    Ok(None)
 }
 
-#[allow(unused_variables)]
 pub fn deserialize_v2j(data: &str) -> Result<Macaroon, MacaroonError> {
-    unimplemented!()
+    let json = match Json::from_str(data) {
+        Ok(json) => json,
+        Err(error) => return Err(MacaroonError::DeserializationError(String::from(error.description()))),
+    };
+    let mut macaroon: Macaroon = Default::default();
+
+    if let Some(location) = try!(get_text_field(&json, "l")) {
+        macaroon.location = location;
+    }
+    macaroon.identifier = match try!(get_byte_field(&json, "i")) {
+        Some(identifier) => identifier,
+        None => {
+            return Err(MacaroonError::DeserializationError(String::from("Missing identifier field")))
+        }
+    };
+
+    if let Some(Json::Array(caveats)) = json.find("c") {
+        for caveat_json in caveats {
+            let id = match try!(get_byte_field(caveat_json, "i")) {
+                Some(id) => id,
+                None => {
+                    return Err(MacaroonError::DeserializationError(String::from("Missing caveat identifier field")))
+                }
+            };
+            let location = try!(get_text_field(caveat_json, "l"));
+            let verifier_id = try!(get_byte_field(caveat_json, "v"));
+            macaroon.caveats.push(Caveat { id, location, verifier_id });
+        }
+    }
+
+    macaroon.signature = match json.find("s64") {
+        Some(value) => try!(base64_decode(try!(json_as_str(value)))),
+        None => return Err(MacaroonError::DeserializationError(String::from("Missing signature field"))),
+    };
+
+    Ok(macaroon)
 }
 
 #[cfg(test)]
 mod tests {
-    use serialize::base64::FromBase64;
+    use serialize::base64::{ToBase64, STANDARD};
     use super::super::macaroon::{Format, Macaroon};
 
-    const SERIALIZED_V1: &'static str = "MDAyMWxvY2F0aW9uIGh0dHA6Ly9leGFtcGxlLm9yZy8KMDAxNWlkZW50aWZpZXIga2V5aWQKMDAyZnNpZ25hdHVyZSB83ueSURxbxvUoSFgF3-myTnheKOKpkwH51xHGCeOO9wo";
-    const SERIALIZED_V1_WITH_CAVEAT: &'static str = "MDAyMWxvY2F0aW9uIGh0dHA6Ly9leGFtcGxlLm9yZy8KMDAxNWlkZW50aWZpZXIga2V5aWQKMDAxZGNpZCBhY2NvdW50ID0gMzczNTkyODU1OQowMDJmc2lnbmF0dXJlIPVIB_bcbt-Ivw9zBrOCJWKjYlM9v3M5umF2XaS9JZ2HCg";
+    const SERIALIZED_V1: &str = "MDAyMWxvY2F0aW9uIGh0dHA6Ly9leGFtcGxlLm9yZy8KMDAxNWlkZW50aWZpZXIga2V5aWQKMDAyZnNpZ25hdHVyZSB83ueSURxbxvUoSFgF3-myTnheKOKpkwH51xHGCeOO9wo";
+    const SERIALIZED_V1_WITH_CAVEAT: &str = "MDAyMWxvY2F0aW9uIGh0dHA6Ly9leGFtcGxlLm9yZy8KMDAxNWlkZW50aWZpZXIga2V5aWQKMDAxZGNpZCBhY2NvdW50ID0gMzczNTkyODU1OQowMDJmc2lnbmF0dXJlIPVIB_bcbt-Ivw9zBrOCJWKjYlM9v3M5umF2XaS9JZ2HCg";
     const SIGNATURE_V1: [u8; 32] = [124, 222, 231, 146, 81, 28, 91, 198, 245, 40, 72, 88, 5, 223,
                                     233, 178, 78, 120, 94, 40, 226, 169, 147, 1, 249, 215, 17,
                                     198, 9, 227, 142, 247];
@@ -191,15 +435,15 @@ mod tests {
 
     #[test]
     fn test_deserialize_v1() {
-        let macaroon = super::deserialize_v1(&SERIALIZED_V1).unwrap();
+        let macaroon = super::deserialize_v1(SERIALIZED_V1).unwrap();
         assert_eq!("http://example.org/", &macaroon.location);
-        assert_eq!("keyid", &macaroon.identifier);
+        assert_eq!(b"keyid", macaroon.identifier.as_bytes());
         assert_eq!(SIGNATURE_V1.to_vec(), macaroon.signature);
-        let macaroon = super::deserialize_v1(&SERIALIZED_V1_WITH_CAVEAT).unwrap();
+        let macaroon = super::deserialize_v1(SERIALIZED_V1_WITH_CAVEAT).unwrap();
         assert_eq!("http://example.org/", &macaroon.location);
-        assert_eq!("keyid", &macaroon.identifier);
+        assert_eq!(b"keyid", macaroon.identifier.as_bytes());
         assert_eq!(1, macaroon.caveats.len());
-        assert_eq!("account = 3735928559", macaroon.caveats[0].id);
+        assert_eq!(b"account = 3735928559", macaroon.caveats[0].id.as_bytes());
         assert_eq!(None, macaroon.caveats[0].verifier_id);
         assert_eq!(None, macaroon.caveats[0].location);
         assert_eq!(SIGNATURE_V1_WITH_CAVEAT.to_vec(), macaroon.signature);
@@ -213,4 +457,128 @@ mod tests {
         let other = Macaroon::deserialize(&serialized).unwrap();
         assert_eq!(macaroon, other);
     }
+
+    // 02 (version) 02 05 "keyid" (identifier) 00 (EOS) 00 (no caveats) 06 20 <32-byte sig>
+    fn serialized_v2_no_caveats() -> Vec<u8> {
+        let mut data = vec![0x02, 0x02, 0x05];
+        data.extend_from_slice(b"keyid");
+        data.push(0x00);
+        data.push(0x00);
+        data.push(0x06);
+        data.push(0x20);
+        data.extend_from_slice(&SIGNATURE_V1);
+        data
+    }
+
+    #[test]
+    fn test_deserialize_v2() {
+        let macaroon = super::deserialize_v2(&serialized_v2_no_caveats()).unwrap();
+        assert_eq!("", &macaroon.location);
+        assert_eq!(b"keyid", macaroon.identifier.as_bytes());
+        assert_eq!(0, macaroon.caveats.len());
+        assert_eq!(SIGNATURE_V1.to_vec(), macaroon.signature);
+    }
+
+    #[test]
+    fn test_deserialize_v1_rejects_short_signature() {
+        let mut data: Vec<u8> = Vec::new();
+        data.extend(super::serialize_as_packet(super::LOCATION, b"http://example.org/"));
+        data.extend(super::serialize_as_packet(super::IDENTIFIER, b"keyid"));
+        data.extend(super::serialize_as_packet(super::SIGNATURE, b"too short"));
+        let serialized = data.to_base64(STANDARD);
+        assert!(super::deserialize_v1(&serialized).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_v2_rejects_overlong_varint() {
+        // version, then 11 continuation-bit bytes for the field-type varint.
+        let mut data = vec![0x02];
+        data.extend_from_slice(&[0x80; 11]);
+        assert!(super::deserialize_v2(&data).is_err());
+    }
+
+    #[test]
+    fn test_serialize_deserialize_v2() {
+        let macaroon = Macaroon::create("http://example.org/", SIGNATURE_V1, "keyid").unwrap();
+        let serialized = macaroon.serialize_v2().unwrap();
+        let other = Macaroon::deserialize_v2(&serialized).unwrap();
+        assert_eq!(macaroon, other);
+    }
+
+    #[test]
+    fn test_serialize_deserialize_v2_with_caveat() {
+        let mut macaroon = Macaroon::create("http://example.org/", SIGNATURE_V1, "keyid").unwrap();
+        macaroon.add_first_party_caveat("account = 3735928559");
+        let serialized = macaroon.serialize_v2().unwrap();
+        let other = Macaroon::deserialize_v2(&serialized).unwrap();
+        assert_eq!(macaroon, other);
+    }
+
+    #[test]
+    fn test_serialize_deserialize_v2j() {
+        let macaroon = Macaroon::create("http://example.org/", SIGNATURE_V1, "keyid").unwrap();
+        let serialized = macaroon.serialize(Format::V2J).unwrap();
+        println!("{:?}", serialized);
+        let other = Macaroon::deserialize_as(&serialized, Format::V2J).unwrap();
+        assert_eq!(macaroon, other);
+    }
+
+    #[test]
+    fn test_serialize_deserialize_v2j_with_caveat() {
+        let mut macaroon = Macaroon::create("http://example.org/", SIGNATURE_V1, "keyid").unwrap();
+        macaroon.add_first_party_caveat("account = 3735928559");
+        let serialized = macaroon.serialize(Format::V2J).unwrap();
+        let other = Macaroon::deserialize_as(&serialized, Format::V2J).unwrap();
+        assert_eq!(macaroon, other);
+    }
+
+    #[test]
+    fn test_deserialize_v2j_accepts_base64_keys() {
+        let json = format!("{{\"v\":2,\"i64\":\"{}\",\"c\":[],\"s64\":\"{}\"}}",
+                            "keyid".as_bytes().to_base64(STANDARD),
+                            SIGNATURE_V1.to_base64(STANDARD));
+        let macaroon = super::deserialize_v2j(&json).unwrap();
+        assert_eq!(b"keyid", macaroon.identifier.as_bytes());
+        assert_eq!(SIGNATURE_V1.to_vec(), macaroon.signature);
+    }
+
+    #[test]
+    fn test_deserialize_v2j_accepts_raw_vid() {
+        let json = "{\"v\":2,\"i\":\"keyid\",\"c\":[{\"i\":\"third-party-id\",\"v\":\"raw-vid\"}],\"s64\":\""
+            .to_string() + &SIGNATURE_V1.to_base64(STANDARD) + "\"}";
+        let macaroon = super::deserialize_v2j(&json).unwrap();
+        assert_eq!(1, macaroon.caveats.len());
+        assert_eq!(b"raw-vid", macaroon.caveats[0].verifier_id.as_ref().unwrap().as_bytes());
+    }
+
+    const BINARY_CAVEAT_ID: [u8; 4] = [0xff, 0x00, 0xfe, 0x01];
+
+    #[test]
+    fn test_serialize_deserialize_v1_with_binary_caveat_id() {
+        let mut macaroon = Macaroon::create("http://example.org/", SIGNATURE_V1, "keyid").unwrap();
+        macaroon.add_first_party_caveat(&BINARY_CAVEAT_ID[..]);
+        let serialized = macaroon.serialize(Format::V1).unwrap();
+        let other = Macaroon::deserialize(&serialized).unwrap();
+        assert_eq!(macaroon, other);
+        assert_eq!(&BINARY_CAVEAT_ID[..], other.caveats[0].id.as_bytes());
+    }
+
+    #[test]
+    fn test_serialize_deserialize_v2_with_binary_caveat_id() {
+        let mut macaroon = Macaroon::create("http://example.org/", SIGNATURE_V1, "keyid").unwrap();
+        macaroon.add_first_party_caveat(&BINARY_CAVEAT_ID[..]);
+        let serialized = macaroon.serialize_v2().unwrap();
+        let other = Macaroon::deserialize_v2(&serialized).unwrap();
+        assert_eq!(macaroon, other);
+    }
+
+    #[test]
+    fn test_serialize_deserialize_v2j_with_binary_caveat_id() {
+        let mut macaroon = Macaroon::create("http://example.org/", SIGNATURE_V1, "keyid").unwrap();
+        macaroon.add_first_party_caveat(&BINARY_CAVEAT_ID[..]);
+        let serialized = macaroon.serialize(Format::V2J).unwrap();
+        println!("{:?}", serialized);
+        let other = Macaroon::deserialize_as(&serialized, Format::V2J).unwrap();
+        assert_eq!(macaroon, other);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,250 @@
+use std::collections::HashSet;
+
+use super::error::MacaroonError;
+use super::macaroon::{hmac, Macaroon, MacaroonKey};
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    let mut difference = 0u8;
+    for (l, r) in left.iter().zip(right.iter()) {
+        difference |= l ^ r;
+    }
+    difference == 0
+}
+
+type GeneralPredicate = Box<Fn(&[u8]) -> bool>;
+
+/// Checks a macaroon's signature and first-party caveats against a root key
+/// and a set of satisfied predicates.
+pub struct Verifier {
+    exact_predicates: HashSet<Vec<u8>>,
+    general_predicates: Vec<GeneralPredicate>,
+}
+
+impl Default for Verifier {
+    fn default() -> Verifier {
+        Verifier::new()
+    }
+}
+
+impl Verifier {
+    pub fn new() -> Verifier {
+        Verifier {
+            exact_predicates: HashSet::new(),
+            general_predicates: Vec::new(),
+        }
+    }
+
+    /// Registers a caveat predicate that must match exactly.
+    pub fn satisfy_exact(&mut self, predicate: &[u8]) {
+        self.exact_predicates.insert(predicate.to_vec());
+    }
+
+    /// Registers a callback that decides whether a caveat predicate is satisfied.
+    pub fn satisfy_general<F>(&mut self, predicate: F)
+        where F: Fn(&[u8]) -> bool + 'static
+    {
+        self.general_predicates.push(Box::new(predicate));
+    }
+
+    fn satisfies(&self, predicate: &[u8]) -> bool {
+        self.exact_predicates.contains(predicate) ||
+        self.general_predicates.iter().any(|satisfy| satisfy(predicate))
+    }
+
+    /// Recomputes `macaroon`'s signature chain from `key`, folding `vid ++ id`
+    /// for third-party caveats (per `Macaroon::add_third_party_caveat`) and
+    /// plain `id` for first-party caveats. First-party caveats must be
+    /// satisfied by this verifier; third-party caveats are left for
+    /// `verify_discharge` to check against their discharge macaroons.
+    pub fn verify(&self, macaroon: &Macaroon, key: MacaroonKey) -> Result<(), MacaroonError> {
+        let mut signature = hmac(&key, macaroon.identifier.as_bytes());
+        for caveat in &macaroon.caveats {
+            signature = match caveat.verifier_id {
+                Some(ref verifier_id) => {
+                    let mut hmac_input = verifier_id.as_bytes().to_vec();
+                    hmac_input.extend_from_slice(caveat.id.as_bytes());
+                    hmac(&signature, &hmac_input)
+                }
+                None => hmac(&signature, caveat.id.as_bytes()),
+            };
+        }
+        if !constant_time_eq(&signature, &macaroon.signature) {
+            return Err(MacaroonError::VerificationError(String::from("Signature mismatch")));
+        }
+
+        for caveat in &macaroon.caveats {
+            if caveat.verifier_id.is_some() {
+                continue;
+            }
+            if !self.satisfies(caveat.id.as_bytes()) {
+                return Err(MacaroonError::VerificationError(format!("Unsatisfied caveat: {:?}", caveat.id)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verifies a discharge macaroon bound to `root` (via `Macaroon::bind`).
+    /// Authenticates `root` itself against `root_key` (so a forged root with
+    /// an arbitrary signature is rejected), then recomputes the discharge's
+    /// own signature chain from `discharge_key`, checks the bind, and checks
+    /// the discharge's first-party caveats against this verifier's predicates.
+    pub fn verify_discharge(&self,
+                             root: &Macaroon,
+                             root_key: MacaroonKey,
+                             discharge: &Macaroon,
+                             discharge_key: MacaroonKey)
+                             -> Result<(), MacaroonError> {
+        try!(self.verify(root, root_key));
+
+        let mut signature = hmac(&discharge_key, discharge.identifier.as_bytes());
+        for caveat in &discharge.caveats {
+            signature = hmac(&signature, caveat.id.as_bytes());
+        }
+        let bound_signature = hmac(&root.signature, &signature);
+        if !constant_time_eq(&bound_signature, &discharge.signature) {
+            return Err(MacaroonError::VerificationError(String::from("Discharge macaroon signature mismatch")));
+        }
+
+        for caveat in &discharge.caveats {
+            if caveat.verifier_id.is_some() {
+                return Err(MacaroonError::VerificationError(String::from("Nested third-party caveats are not supported")));
+            }
+            if !self.satisfies(caveat.id.as_bytes()) {
+                return Err(MacaroonError::VerificationError(format!("Unsatisfied caveat: {:?}", caveat.id)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Verifier;
+    use super::super::macaroon::Macaroon;
+
+    const KEY: [u8; 32] = [0; 32];
+
+    #[test]
+    fn test_verify_no_caveats() {
+        let macaroon = Macaroon::create("http://example.org/", KEY, "keyid").unwrap();
+        let verifier = Verifier::new();
+        assert!(verifier.verify(&macaroon, KEY).is_ok());
+    }
+
+    #[test]
+    fn test_verify_bad_key() {
+        let macaroon = Macaroon::create("http://example.org/", KEY, "keyid").unwrap();
+        let verifier = Verifier::new();
+        assert!(verifier.verify(&macaroon, [1; 32]).is_err());
+    }
+
+    #[test]
+    fn test_verify_exact_caveat() {
+        let mut macaroon = Macaroon::create("http://example.org/", KEY, "keyid").unwrap();
+        macaroon.add_first_party_caveat("account = 3735928559");
+        assert!(Verifier::new().verify(&macaroon, KEY).is_err());
+
+        let mut verifier = Verifier::new();
+        verifier.satisfy_exact(b"account = 3735928559");
+        assert!(verifier.verify(&macaroon, KEY).is_ok());
+    }
+
+    #[test]
+    fn test_verify_general_caveat() {
+        let mut macaroon = Macaroon::create("http://example.org/", KEY, "keyid").unwrap();
+        macaroon.add_first_party_caveat("account = 3735928559");
+
+        let mut verifier = Verifier::new();
+        verifier.satisfy_general(|predicate| predicate.starts_with(b"account = "));
+        assert!(verifier.verify(&macaroon, KEY).is_ok());
+    }
+
+    #[test]
+    fn test_verify_root_with_third_party_caveat() {
+        const DISCHARGE_KEY: [u8; 32] = [1; 32];
+
+        let mut root = Macaroon::create("http://example.org/", KEY, "keyid").unwrap();
+        root.add_third_party_caveat("http://auth.example.org/", DISCHARGE_KEY, "third-party-id");
+
+        assert!(Verifier::new().verify(&root, KEY).is_ok());
+        assert!(Verifier::new().verify(&root, DISCHARGE_KEY).is_err());
+    }
+
+    #[test]
+    fn test_verify_discharge() {
+        const DISCHARGE_KEY: [u8; 32] = [1; 32];
+
+        let mut root = Macaroon::create("http://example.org/", KEY, "keyid").unwrap();
+        root.add_third_party_caveat("http://auth.example.org/", DISCHARGE_KEY, "third-party-id");
+
+        let mut discharge = Macaroon::create_discharge("http://auth.example.org/",
+                                                         DISCHARGE_KEY,
+                                                         "third-party-id")
+            .unwrap();
+        discharge.add_first_party_caveat("time < 2030-01-01");
+        let bound = discharge.bind(&root);
+
+        let mut verifier = Verifier::new();
+        verifier.satisfy_exact(b"time < 2030-01-01");
+        assert!(verifier.verify_discharge(&root, KEY, &bound, DISCHARGE_KEY).is_ok());
+    }
+
+    #[test]
+    fn test_verify_discharge_unsatisfied_caveat() {
+        const DISCHARGE_KEY: [u8; 32] = [1; 32];
+
+        let mut root = Macaroon::create("http://example.org/", KEY, "keyid").unwrap();
+        root.add_third_party_caveat("http://auth.example.org/", DISCHARGE_KEY, "third-party-id");
+
+        let mut discharge = Macaroon::create_discharge("http://auth.example.org/",
+                                                         DISCHARGE_KEY,
+                                                         "third-party-id")
+            .unwrap();
+        discharge.add_first_party_caveat("time < 2030-01-01");
+        let bound = discharge.bind(&root);
+
+        let verifier = Verifier::new();
+        assert!(verifier.verify_discharge(&root, KEY, &bound, DISCHARGE_KEY).is_err());
+    }
+
+    #[test]
+    fn test_verify_discharge_wrong_root() {
+        const DISCHARGE_KEY: [u8; 32] = [1; 32];
+
+        let mut root = Macaroon::create("http://example.org/", KEY, "keyid").unwrap();
+        root.add_third_party_caveat("http://auth.example.org/", DISCHARGE_KEY, "third-party-id");
+        let other_root = Macaroon::create("http://example.org/", KEY, "other-keyid").unwrap();
+
+        let discharge = Macaroon::create_discharge("http://auth.example.org/",
+                                                     DISCHARGE_KEY,
+                                                     "third-party-id")
+            .unwrap();
+        let bound = discharge.bind(&other_root);
+
+        let verifier = Verifier::new();
+        assert!(verifier.verify_discharge(&root, KEY, &bound, DISCHARGE_KEY).is_err());
+    }
+
+    #[test]
+    fn test_verify_discharge_forged_root_signature() {
+        const DISCHARGE_KEY: [u8; 32] = [1; 32];
+
+        let mut root = Macaroon::create("http://example.org/", KEY, "keyid").unwrap();
+        root.add_third_party_caveat("http://auth.example.org/", DISCHARGE_KEY, "third-party-id");
+        root.signature = vec![0u8; root.signature.len()];
+
+        let discharge = Macaroon::create_discharge("http://auth.example.org/",
+                                                     DISCHARGE_KEY,
+                                                     "third-party-id")
+            .unwrap();
+        let bound = discharge.bind(&root);
+
+        let verifier = Verifier::new();
+        assert!(verifier.verify_discharge(&root, KEY, &bound, DISCHARGE_KEY).is_err());
+    }
+}